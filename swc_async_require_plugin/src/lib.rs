@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod tests {
     use super::*;
-    use swc_core::ecma::parser::{Parser, StringInput, Syntax, EsConfig};
+    use swc_core::ecma::parser::{Parser, StringInput, Syntax, EsConfig, TsConfig};
     use swc_core::ecma::codegen::{Emitter, text_writer::JsWriter, Config as CodegenConfig};
     use swc_core::ecma::visit::VisitMutWith;
     use swc_core::ecma::ast::Program;
@@ -15,10 +15,17 @@ mod tests {
         parser.parse_program().unwrap()
     }
 
+    fn parse_ts(src: &str) -> Program {
+        let cm = Arc::new(SourceMap::default());
+        let fm = cm.new_source_file(FileName::Custom("test.ts".into()), src.into());
+        let mut parser = Parser::new(Syntax::Typescript(TsConfig::default()), StringInput::from(&*fm), None);
+        parser.parse_program().unwrap()
+    }
+
     #[test]
     fn test_require_call_marked() {
     let mut program = parse_js("const x = require('foo');");
-    let mut transformer = super::AsyncRequireTransform::new();
+    let mut transformer = super::AsyncRequireTransform::new(super::AsyncRequireConfig::default());
     program.visit_mut_with(&mut transformer);
         // 変換後ASTに__require__が現れることを確認
         let mut found = false;
@@ -51,7 +58,7 @@ mod tests {
 
         for (src, expect_sub) in srcs {
             let mut program = parse_js(src);
-            let mut transformer = super::AsyncRequireTransform::new();
+            let mut transformer = super::AsyncRequireTransform::new(super::AsyncRequireConfig::default());
             program.visit_mut_with(&mut transformer);
             let mut found = false;
             struct Finder<'a> { expected: &'a str, found: &'a mut bool }
@@ -77,54 +84,651 @@ mod tests {
             assert!(found, "expected '{}' to be present in transformed AST for source: {}", expect_sub, src);
         }
     }
+
+    #[test]
+    fn test_resolver_runs_inside_exported_function_body() {
+        struct PrefixResolve;
+        impl super::Resolve for PrefixResolve {
+            fn resolve(&self, specifier: &str) -> String {
+                format!("resolved:{}", specifier)
+            }
+        }
+
+        let mut program = parse_js("export function run() { return require('bar'); }");
+        let mut transformer = super::AsyncRequireTransform::with_resolver(
+            super::AsyncRequireConfig::default(),
+            Box::new(PrefixResolve),
+        );
+        program.visit_mut_with(&mut transformer);
+
+        let mut found = false;
+        struct FindResolvedSpecifier<'a> { found: &'a mut bool }
+        impl<'a> VisitMut for FindResolvedSpecifier<'a> {
+            fn visit_mut_str(&mut self, n: &mut Str) {
+                if n.value.starts_with("resolved:") {
+                    *self.found = true;
+                }
+            }
+        }
+        program.visit_mut_with(&mut FindResolvedSpecifier { found: &mut found });
+        assert!(found, "resolver was not applied to a require() nested inside an exported function body");
+    }
+
+    #[test]
+    fn test_transformed_ast_emits_a_source_map() {
+        let cm = Arc::new(SourceMap::default());
+        let fm = cm.new_source_file(FileName::Custom("test.js".into()), "import foo from 'bar';\nfoo();".into());
+        let mut parser = Parser::new(Syntax::Es(EsConfig::default()), StringInput::from(&*fm), None);
+        let mut program = parser.parse_program().unwrap();
+        let mut transformer = super::AsyncRequireTransform::new(super::AsyncRequireConfig::default());
+        program.visit_mut_with(&mut transformer);
+
+        let map = super::build_source_map(&cm, &program);
+        assert!(map.contains("\"mappings\""), "expected a standard source map JSON, got: {}", map);
+    }
+
+    #[test]
+    fn test_transform_with_source_map_uses_the_caller_supplied_source_map() {
+        let cm = Arc::new(SourceMap::default());
+        let fm = cm.new_source_file(FileName::Custom("test.js".into()), "import foo from 'bar';\nfoo();".into());
+        let mut parser = Parser::new(Syntax::Es(EsConfig::default()), StringInput::from(&*fm), None);
+        let program = parser.parse_program().unwrap();
+
+        let (transformed, map) = super::transform_with_source_map(&cm, program, super::AsyncRequireConfig::default());
+        assert!(map.contains("test.js"), "expected the caller's real filename in the map, got: {}", map);
+
+        let mut found = false;
+        struct FindRequire<'a> { found: &'a mut bool }
+        impl<'a> VisitMut for FindRequire<'a> {
+            fn visit_mut_call_expr(&mut self, n: &mut CallExpr) {
+                if let Callee::Expr(expr) = &n.callee {
+                    if let Expr::Ident(Ident { sym, .. }) = &**expr {
+                        if sym == "__require__" {
+                            *self.found = true;
+                        }
+                    }
+                }
+                n.visit_mut_children_with(self);
+            }
+        }
+        let mut transformed = transformed;
+        transformed.visit_mut_with(&mut FindRequire { found: &mut found });
+        assert!(found, "transform_with_source_map should still run the AsyncRequireTransform");
+    }
+
+    fn emit(program: &Program) -> String {
+        let cm = Arc::new(SourceMap::default());
+        let mut buf = vec![];
+        {
+            let writer = JsWriter::new(cm.clone(), "\n", &mut buf, None);
+            let mut emitter = Emitter {
+                cfg: CodegenConfig::default(),
+                comments: None,
+                cm: cm.clone(),
+                wr: writer,
+            };
+            emitter.emit_program(program).expect("codegen should not fail on a transformed AST");
+        }
+        String::from_utf8(buf).expect("emitted JS is always valid UTF-8")
+    }
+
+    #[test]
+    fn test_imports_and_exported_fns_are_hoisted_before_other_statements() {
+        let mut program = parse_js(
+            "firstCall();\nimport foo from 'bar';\nexport function f() {}\nsecondCall();",
+        );
+        let mut transformer = super::AsyncRequireTransform::new(super::AsyncRequireConfig::default());
+        program.visit_mut_with(&mut transformer);
+        let code = emit(&program);
+
+        let require_pos = code.find("__require__(").expect("require call missing from output");
+        let fn_pos = code.find("function f").expect("exported function missing from output");
+        let first_call_pos = code.find("firstCall").expect("firstCall missing from output");
+        let second_call_pos = code.find("secondCall").expect("secondCall missing from output");
+
+        assert!(require_pos < fn_pos, "import should be hoisted before the exported function, got: {}", code);
+        assert!(fn_pos < first_call_pos, "exported function should be hoisted before both plain statements, got: {}", code);
+        assert!(first_call_pos < second_call_pos, "plain statements should keep their relative order, got: {}", code);
+    }
+
+    #[test]
+    fn test_no_interop_toggle_controls_the_interop_wrapper() {
+        let mut with_interop = parse_js("import foo from 'bar';");
+        let mut transformer = super::AsyncRequireTransform::new(super::AsyncRequireConfig::default());
+        with_interop.visit_mut_with(&mut transformer);
+        let code = emit(&with_interop);
+        assert!(code.contains("__interopDefault__"), "expected interop wrapper by default, got: {}", code);
+
+        let mut without_interop = parse_js("import foo from 'bar';");
+        let mut transformer = super::AsyncRequireTransform::new(super::AsyncRequireConfig { no_interop: true, ..Default::default() });
+        without_interop.visit_mut_with(&mut transformer);
+        let code = emit(&without_interop);
+        assert!(!code.contains("__interopDefault__"), "no_interop should skip the interop wrapper, got: {}", code);
+    }
+
+    #[test]
+    fn test_export_star_from_emits_a_keys_copy_loop() {
+        let mut program = parse_js("export * from 'bar';");
+        let mut transformer = super::AsyncRequireTransform::new(super::AsyncRequireConfig::default());
+        program.visit_mut_with(&mut transformer);
+        let code = emit(&program);
+
+        assert!(code.contains("__require__("), "expected the re-exported module to go through __require__, got: {}", code);
+        assert!(code.contains("Object.keys"), "expected a Object.keys copy loop for export * from, got: {}", code);
+        assert!(code.contains("default"), "expected the copy loop to guard against re-copying default, got: {}", code);
+    }
+
+    #[test]
+    fn test_factory_is_async_only_when_top_level_await_is_present() {
+        let mut sync_program = parse_js("const x = 1;");
+        let mut transformer = super::AsyncRequireTransform::new(super::AsyncRequireConfig::default());
+        sync_program.visit_mut_with(&mut transformer);
+        let code = emit(&sync_program);
+        assert!(code.contains("__define__"), "expected the module to be wrapped in __define__, got: {}", code);
+        assert!(!code.contains("async"), "module with no top-level await should not get an async factory, got: {}", code);
+
+        let mut async_program = parse_js("const x = await import('bar');");
+        let mut transformer = super::AsyncRequireTransform::new(super::AsyncRequireConfig::default());
+        async_program.visit_mut_with(&mut transformer);
+        let code = emit(&async_program);
+        assert!(code.contains("async"), "module with a top-level await should get an async factory, got: {}", code);
+    }
+
+    #[test]
+    fn test_type_only_imports_and_exports_are_stripped() {
+        let mut import_program = parse_ts("import type { T } from 'bar';\nimport { type U, foo } from 'bar';\nfoo();");
+        let mut transformer = super::AsyncRequireTransform::new(super::AsyncRequireConfig::default());
+        import_program.visit_mut_with(&mut transformer);
+        let code = emit(&import_program);
+        assert_eq!(code.matches("__require__(").count(), 1, "a type-only import should not generate its own require, got: {}", code);
+
+        let mut export_program = parse_ts("export type { T } from 'bar';");
+        let mut transformer = super::AsyncRequireTransform::new(super::AsyncRequireConfig::default());
+        export_program.visit_mut_with(&mut transformer);
+        let code = emit(&export_program);
+        assert!(!code.contains("__require__("), "export type {{ ... }} from 'mod' has no runtime representation, got: {}", code);
+    }
+
+    #[test]
+    fn test_exported_let_registers_a_getter_not_a_value_copy() {
+        let mut program = parse_js("export let counter = 1;\ncounter = 2;");
+        let mut transformer = super::AsyncRequireTransform::new(super::AsyncRequireConfig::default());
+        program.visit_mut_with(&mut transformer);
+        let code = emit(&program);
+
+        assert!(code.contains("Object.defineProperty"), "expected a live-binding getter, got: {}", code);
+        assert!(code.contains("get:"), "expected the descriptor to carry a getter, got: {}", code);
+        assert!(!code.contains("module.exports.counter ="), "a value-copy assignment would freeze the exported value at export time, got: {}", code);
+    }
+
+    #[test]
+    fn test_destructured_export_registers_a_getter_per_bound_name() {
+        let mut program = parse_js("export const { a, b } = obj;");
+        let mut transformer = super::AsyncRequireTransform::new(super::AsyncRequireConfig::default());
+        program.visit_mut_with(&mut transformer);
+        let code = emit(&program);
+
+        let define_property_count = code.matches("Object.defineProperty").count();
+        assert_eq!(define_property_count, 2, "expected a getter for each destructured binding (a and b), got: {}", code);
+    }
 }
-use swc_core::common::DUMMY_SP;
+use swc_core::common::sync::Lrc;
+use swc_core::common::{Span, SourceMap, DUMMY_SP};
 use swc_core::ecma::ast::*;
-use swc_core::ecma::visit::{as_folder, VisitMut, VisitMutWith};
+use swc_core::ecma::codegen::{text_writer::JsWriter, Config as CodegenConfig, Emitter};
+use swc_core::ecma::visit::{as_folder, Visit, VisitMut, VisitMutWith, VisitWith};
 use wasm_bindgen::prelude::*;
 
+/// User-facing knobs for [`AsyncRequireTransform`].
+#[derive(Debug, Clone, Default)]
+pub struct AsyncRequireConfig {
+    /// Skip the `__interopDefault__`/`__interopNamespace__` wrapping around
+    /// default and namespace imports. Safe to set when every module the host
+    /// resolves is already a real ES module (e.g. it also ran through this
+    /// same transform), trading correctness against CommonJS interop for
+    /// leaner output.
+    pub no_interop: bool,
+    /// The id the host's module registry knows this module by, baked into
+    /// the `__define__('<id>', ...)` wrapper. Left empty lets a host that
+    /// assigns ids out-of-band (e.g. patches it in post-codegen) ignore it.
+    pub module_id: String,
+}
+
+/// Rewrites a module specifier before it is baked into a `__require__(...)`
+/// literal, mirroring Aleph's `resolve_fold`. Lets the host map bare package
+/// names onto its own module registry (a virtual path, a CDN URL, whatever
+/// the runtime resolves) instead of shipping the specifier as the author
+/// wrote it.
+pub trait Resolve {
+    fn resolve(&self, specifier: &str) -> String;
+}
+
+/// The default resolver: every specifier passes through unchanged.
+pub struct NoopResolve;
+
+impl Resolve for NoopResolve {
+    fn resolve(&self, specifier: &str) -> String {
+        specifier.to_string()
+    }
+}
+
 pub struct AsyncRequireTransform {
     tmp_counter: usize,
+    config: AsyncRequireConfig,
+    resolver: Box<dyn Resolve>,
 }
 
 impl AsyncRequireTransform {
-    pub fn new() -> Self {
-        Self { tmp_counter: 0 }
+    pub fn new(config: AsyncRequireConfig) -> Self {
+        Self::with_resolver(config, Box::new(NoopResolve))
+    }
+
+    pub fn with_resolver(config: AsyncRequireConfig, resolver: Box<dyn Resolve>) -> Self {
+        Self { tmp_counter: 0, config, resolver }
     }
+
     fn next_tmp(&mut self) -> String {
         let id = format!("__mod_{}__", self.tmp_counter);
         self.tmp_counter += 1;
         id
     }
+
+    /// Resolves a raw import/require/dynamic-import source string through
+    /// the configured [`Resolve`], producing the literal that ends up inside
+    /// the generated `__require__('...')` call.
+    fn resolve_specifier(&self, specifier: &JsWord) -> JsWord {
+        self.resolver.resolve(specifier).into()
+    }
+}
+
+/// `true` for the leading string-literal expression statements ESM treats as a
+/// directive prologue (e.g. `"use strict";`). These must stay pinned to the
+/// very top of the module regardless of where imports get hoisted to.
+fn is_directive(item: &ModuleItem) -> bool {
+    matches!(
+        item,
+        ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+            expr,
+            ..
+        })) if matches!(&**expr, Expr::Lit(Lit::Str(_)))
+    )
+}
+
+/// Moves every `import` declaration (and every exported function declaration)
+/// above the first non-import/non-directive statement, modeled on swc's
+/// `module_hoister`. ESM evaluates all imports before any module body code
+/// runs, but once imports are rewritten to ordinary `const x = await
+/// __require__(...)` statements that guarantee is lost unless we physically
+/// reorder them first. Directives and relative order within each bucket are
+/// preserved.
+fn hoist_imports_and_exported_fns(body: Vec<ModuleItem>) -> Vec<ModuleItem> {
+    let mut items = body.into_iter();
+    let mut directives = Vec::new();
+    let mut rest = Vec::new();
+    let mut in_prologue = true;
+    for item in items.by_ref() {
+        if in_prologue && is_directive(&item) {
+            directives.push(item);
+        } else {
+            in_prologue = false;
+            rest.push(item);
+        }
+    }
+
+    let mut found_other = false;
+    let mut top_imports = Vec::new();
+    let mut hoisted_imports = Vec::new();
+    let mut top_exported_fns = Vec::new();
+    let mut hoisted_exported_fns = Vec::new();
+    let mut others = Vec::new();
+
+    for item in rest {
+        let is_import = matches!(item, ModuleItem::ModuleDecl(ModuleDecl::Import(_)));
+        let is_exported_fn = matches!(
+            &item,
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(ExportNamedDecl {
+                decl: Some(Decl::Fn(_)),
+                ..
+            }))
+        );
+
+        if is_import {
+            if found_other {
+                hoisted_imports.push(item);
+            } else {
+                top_imports.push(item);
+            }
+        } else if is_exported_fn {
+            if found_other {
+                hoisted_exported_fns.push(item);
+            } else {
+                top_exported_fns.push(item);
+            }
+        } else {
+            found_other = true;
+            others.push(item);
+        }
+    }
+
+    let mut new_body = directives;
+    new_body.extend(top_imports);
+    new_body.extend(hoisted_imports);
+    new_body.extend(top_exported_fns);
+    new_body.extend(hoisted_exported_fns);
+    new_body.extend(others);
+    new_body
+}
+
+/// `__interopDefault__(<mod>)` — mirrors Babel/swc's `_interopRequireDefault`.
+/// At runtime this returns `mod.default` when `mod.__esModule` is set and
+/// `mod` itself otherwise, so a default import binds correctly whether
+/// `'bar'` turned out to be a transpiled ES module or a plain CJS module.
+fn interop_default_call(required: Expr) -> Expr {
+    Expr::Call(CallExpr {
+        span: DUMMY_SP,
+        callee: Callee::Expr(Box::new(Expr::Ident(Ident::new("__interopDefault__".into(), DUMMY_SP)))),
+        args: vec![ExprOrSpread { spread: None, expr: Box::new(required) }],
+        type_args: None,
+    })
+}
+
+/// `__interopNamespace__(<mod>)` — mirrors swc's `_interopRequireWildcard`.
+/// For a non-ESM module this builds a synthetic namespace object copying the
+/// module's own enumerable keys plus a `default` pointing at the module
+/// itself, so `import * as ns` sees the same shape regardless of module kind.
+fn interop_namespace_call(required: Expr) -> Expr {
+    Expr::Call(CallExpr {
+        span: DUMMY_SP,
+        callee: Callee::Expr(Box::new(Expr::Ident(Ident::new("__interopNamespace__".into(), DUMMY_SP)))),
+        args: vec![ExprOrSpread { spread: None, expr: Box::new(required) }],
+        type_args: None,
+    })
+}
+
+/// Walks a statement looking for a genuine top-level `await` — an
+/// `AwaitExpr` or `for await` not nested inside a function, arrow, or class
+/// body — mirroring swc's `contains_top_level_await`. Nested scopes have
+/// their own, independently-async execution context, so an `await` inside
+/// one says nothing about whether the enclosing module factory needs to be
+/// `async`.
+struct TopLevelAwaitFinder {
+    found: bool,
+}
+
+impl Visit for TopLevelAwaitFinder {
+    fn visit_await_expr(&mut self, n: &AwaitExpr) {
+        self.found = true;
+        n.visit_children_with(self);
+    }
+
+    fn visit_for_of_stmt(&mut self, n: &ForOfStmt) {
+        if n.is_await {
+            self.found = true;
+        }
+        n.visit_children_with(self);
+    }
+
+    fn visit_function(&mut self, _n: &Function) {}
+    fn visit_arrow_expr(&mut self, _n: &ArrowExpr) {}
+    fn visit_class(&mut self, _n: &Class) {}
+}
+
+fn contains_top_level_await(stmts: &[Stmt]) -> bool {
+    let mut finder = TopLevelAwaitFinder { found: false };
+    for stmt in stmts {
+        if finder.found {
+            break;
+        }
+        stmt.visit_with(&mut finder);
+    }
+    finder.found
+}
+
+/// Wraps the already-lowered module body in `__define__('<id>', (module,
+/// exports, __require__, __import__) => { ...body; return module.exports;
+/// })`, establishing the execution context the generated `await
+/// __require__(...)` calls need and letting the runtime cache
+/// `module.exports` once the factory settles. The factory is only marked
+/// `async` when the lowered body contains a top-level await. Every import
+/// and every `export ... from` lowers to an `await __require__(...)`, so a
+/// module with any of those always gets an async factory; only a module with
+/// none of them and no top-level await of its own gets a synchronous one.
+fn wrap_in_define_factory(module_id: JsWord, stmts: Vec<Stmt>) -> Stmt {
+    let is_async = contains_top_level_await(&stmts);
+    let mut factory_stmts = stmts;
+    factory_stmts.push(Stmt::Return(ReturnStmt { span: DUMMY_SP, arg: Some(Box::new(module_exports_expr())) }));
+
+    let factory = Expr::Arrow(ArrowExpr {
+        span: DUMMY_SP,
+        params: ["module", "exports", "__require__", "__import__"]
+            .into_iter()
+            .map(|name| Pat::Ident(BindingIdent { id: Ident::new(name.into(), DUMMY_SP), type_ann: None }))
+            .collect(),
+        body: Box::new(BlockStmtOrExpr::BlockStmt(BlockStmt { span: DUMMY_SP, stmts: factory_stmts })),
+        is_async,
+        is_generator: false,
+        type_params: None,
+        return_type: None,
+    });
+
+    let define_call = Expr::Call(CallExpr {
+        span: DUMMY_SP,
+        callee: Callee::Expr(Box::new(Expr::Ident(Ident::new("__define__".into(), DUMMY_SP)))),
+        args: vec![
+            ExprOrSpread { spread: None, expr: Box::new(Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: module_id, raw: None }))) },
+            ExprOrSpread { spread: None, expr: Box::new(factory) },
+        ],
+        type_args: None,
+    });
+    Stmt::Expr(ExprStmt { span: DUMMY_SP, expr: Box::new(define_call) })
+}
+
+/// `__require__('<specifier>')` — the call every import/export/require site
+/// rewrites its module source string into.
+/// `span` is the original `ImportDecl`/`src` span, reused (per Aleph's
+/// `mark_import_src_location`) so the rewritten call and its string literal
+/// still map back to the line the author wrote the import/export on.
+fn require_call(specifier: JsWord, span: Span) -> Expr {
+    Expr::Call(CallExpr {
+        span,
+        callee: Callee::Expr(Box::new(Expr::Ident(Ident::new("__require__".into(), DUMMY_SP)))),
+        args: vec![ExprOrSpread { spread: None, expr: Box::new(Expr::Lit(Lit::Str(Str { span, value: specifier, raw: None }))) }],
+        type_args: None,
+    })
+}
+
+/// `for (const k of Object.keys(<tmp>)) if (k !== 'default') module.exports[k]
+/// = <tmp>[k];` — the runtime spread behind `export * from 'src'`. `default`
+/// is excluded because a re-exported module's own default binding never
+/// implicitly becomes this module's default.
+fn export_star_loop_stmt(tmp: Ident) -> Stmt {
+    let key = Ident::new("k".into(), DUMMY_SP);
+    let keys_call = Expr::Call(CallExpr {
+        span: DUMMY_SP,
+        callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
+            span: DUMMY_SP,
+            obj: Box::new(Expr::Ident(Ident::new("Object".into(), DUMMY_SP))),
+            prop: MemberProp::Ident(Ident::new("keys".into(), DUMMY_SP)),
+            computed: false,
+        }))),
+        args: vec![ExprOrSpread { spread: None, expr: Box::new(Expr::Ident(tmp.clone())) }],
+        type_args: None,
+    });
+    let guard = Expr::Bin(BinExpr {
+        span: DUMMY_SP,
+        op: BinaryOp::NotEqEq,
+        left: Box::new(Expr::Ident(key.clone())),
+        right: Box::new(Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: "default".into(), raw: None }))),
+    });
+    let exports_slot = Expr::Member(MemberExpr {
+        span: DUMMY_SP,
+        obj: Box::new(module_exports_expr()),
+        prop: MemberProp::Computed(ComputedPropName { span: DUMMY_SP, expr: Box::new(Expr::Ident(key.clone())) }),
+        computed: true,
+    });
+    let tmp_slot = Expr::Member(MemberExpr {
+        span: DUMMY_SP,
+        obj: Box::new(Expr::Ident(tmp)),
+        prop: MemberProp::Computed(ComputedPropName { span: DUMMY_SP, expr: Box::new(Expr::Ident(key.clone())) }),
+        computed: true,
+    });
+    let assign = Expr::Assign(AssignExpr { span: DUMMY_SP, op: AssignOp::Assign, left: PatOrExpr::Expr(Box::new(exports_slot)), right: Box::new(tmp_slot) });
+    let body = Stmt::If(IfStmt { span: DUMMY_SP, test: Box::new(guard), cons: Box::new(Stmt::Expr(ExprStmt { span: DUMMY_SP, expr: Box::new(assign) })), alt: None });
+    Stmt::ForOf(ForOfStmt {
+        span: DUMMY_SP,
+        is_await: false,
+        left: ForHead::VarDecl(Box::new(VarDecl {
+            span: DUMMY_SP,
+            kind: VarDeclKind::Const,
+            declare: false,
+            decls: vec![VarDeclarator { span: DUMMY_SP, name: Pat::Ident(BindingIdent { id: key, type_ann: None }), init: None, definite: false }],
+        })),
+        right: Box::new(keys_call),
+        body: Box::new(body),
+    })
+}
+
+/// Walks a binding pattern and collects every identifier it binds, so a
+/// destructured export (`export const { a, b } = obj;`, `export const [x] =
+/// arr;`) registers a getter for each bound name instead of only the
+/// top-level `Pat::Ident` case.
+fn collect_bound_idents(pat: &Pat, out: &mut Vec<Ident>) {
+    match pat {
+        Pat::Ident(BindingIdent { id, .. }) => out.push(id.clone()),
+        Pat::Array(arr) => {
+            for elem in arr.elems.iter().flatten() {
+                collect_bound_idents(elem, out);
+            }
+        }
+        Pat::Object(obj) => {
+            for prop in &obj.props {
+                match prop {
+                    ObjectPatProp::KeyValue(kv) => collect_bound_idents(&kv.value, out),
+                    ObjectPatProp::Assign(a) => out.push(a.key.id.clone()),
+                    ObjectPatProp::Rest(r) => collect_bound_idents(&r.arg, out),
+                }
+            }
+        }
+        Pat::Rest(r) => collect_bound_idents(&r.arg, out),
+        Pat::Assign(a) => collect_bound_idents(&a.left, out),
+        Pat::Expr(_) | Pat::Invalid(_) => {}
+    }
+}
+
+/// Builds the `module.exports` member expression shared by every export
+/// lowering site.
+fn module_exports_expr() -> Expr {
+    Expr::Member(MemberExpr {
+        span: DUMMY_SP,
+        obj: Box::new(Expr::Ident(Ident::new("module".into(), DUMMY_SP))),
+        prop: MemberProp::Ident(Ident::new("exports".into(), DUMMY_SP)),
+        computed: false,
+    })
+}
+
+/// `module.exports.<name> = <expr>;` — a one-time value copy. Used where
+/// there is no local binding to re-read later (anonymous `export default
+/// <expr>`), where it is observably identical to a live binding, and for
+/// re-export specifiers (`export { a } from 'src'`), which copy the
+/// re-exported module's property once instead of registering a getter.
+fn export_assign_expr(exported_name: JsWord, value: Expr) -> Expr {
+    Expr::Assign(AssignExpr {
+        span: DUMMY_SP,
+        op: AssignOp::Assign,
+        left: PatOrExpr::Expr(Box::new(Expr::Member(MemberExpr {
+            span: DUMMY_SP,
+            obj: Box::new(module_exports_expr()),
+            prop: MemberProp::Ident(Ident::new(exported_name, DUMMY_SP)),
+            computed: false,
+        }))),
+        right: Box::new(value),
+    })
+}
+
+/// `Object.defineProperty(module.exports, "<name>", { enumerable: true, get:
+/// () => <local> });` — a SystemJS-style live-binding export. Registering a
+/// getter instead of copying the value means reassignments of exported
+/// `let`/`var` bindings and circular imports both observe the current value
+/// rather than a stale snapshot taken at export time.
+fn export_getter_stmt(exported_name: JsWord, local: Ident) -> Stmt {
+    let getter = Expr::Arrow(ArrowExpr {
+        span: DUMMY_SP,
+        params: vec![],
+        body: Box::new(BlockStmtOrExpr::Expr(Box::new(Expr::Ident(local)))),
+        is_async: false,
+        is_generator: false,
+        type_params: None,
+        return_type: None,
+    });
+    let descriptor = Expr::Object(ObjectLit {
+        span: DUMMY_SP,
+        props: vec![
+            PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                key: PropName::Ident(Ident::new("enumerable".into(), DUMMY_SP)),
+                value: Box::new(Expr::Lit(Lit::Bool(Bool { span: DUMMY_SP, value: true }))),
+            }))),
+            PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                key: PropName::Ident(Ident::new("get".into(), DUMMY_SP)),
+                value: Box::new(getter),
+            }))),
+        ],
+    });
+    let define_property = Expr::Call(CallExpr {
+        span: DUMMY_SP,
+        callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
+            span: DUMMY_SP,
+            obj: Box::new(Expr::Ident(Ident::new("Object".into(), DUMMY_SP))),
+            prop: MemberProp::Ident(Ident::new("defineProperty".into(), DUMMY_SP)),
+            computed: false,
+        }))),
+        args: vec![
+            ExprOrSpread { spread: None, expr: Box::new(module_exports_expr()) },
+            ExprOrSpread { spread: None, expr: Box::new(Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: exported_name, raw: None }))) },
+            ExprOrSpread { spread: None, expr: Box::new(descriptor) },
+        ],
+        type_args: None,
+    });
+    Stmt::Expr(ExprStmt { span: DUMMY_SP, expr: Box::new(define_property) })
 }
 
 impl VisitMut for AsyncRequireTransform {
     fn visit_mut_program(&mut self, program: &mut Program) {
         match program {
             Program::Module(m) => {
+                m.body = hoist_imports_and_exported_fns(std::mem::take(&mut m.body));
+
                 let mut new_body: Vec<ModuleItem> = Vec::new();
                 for item in m.body.drain(..) {
                     match item {
+                        ModuleItem::ModuleDecl(ModuleDecl::Import(import_decl)) if import_decl.type_only => {
+                            // `import type { ... } from 'mod'` has no runtime
+                            // representation; drop it entirely.
+                        }
                         ModuleItem::ModuleDecl(ModuleDecl::Import(import_decl)) => {
-                            // Build variable declarators for specifiers
-                            if !import_decl.specifiers.is_empty() {
+                            let specifier = self.resolve_specifier(&import_decl.src.value);
+                            let src_span = import_decl.src.span;
+                            let decl_span = import_decl.span;
+                            let had_specifiers = !import_decl.specifiers.is_empty();
+                            // Build variable declarators for specifiers, skipping any
+                            // marked `is_type_only` (`import { type T, foo } from 'mod'`)
+                            let specifiers: Vec<ImportSpecifier> = import_decl
+                                .specifiers
+                                .into_iter()
+                                .filter(|spec| !matches!(spec, ImportSpecifier::Named(n) if n.is_type_only))
+                                .collect();
+                            if !specifiers.is_empty() {
                                 let mut decls: Vec<VarDeclarator> = Vec::new();
-                                for spec in import_decl.specifiers {
+                                for spec in specifiers {
                                     match spec {
                                         ImportSpecifier::Default(default_spec) => {
                                             let id = default_spec.local;
                                             // const id = await __require__('source');
-                                            let call = Expr::Call(CallExpr {
-                                                span: DUMMY_SP,
-                                                callee: Callee::Expr(Box::new(Expr::Ident(Ident::new("__require__".into(), DUMMY_SP)))),
-                                                args: vec![ExprOrSpread {
-                                                    spread: None,
-                                                    expr: Box::new(Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: import_decl.src.value.clone(), raw: None }))),
-                                                }],
-                                                type_args: None,
-                                            });
-                                            let await_expr = Expr::Await(AwaitExpr { span: DUMMY_SP, arg: Box::new(call) });
-                                            decls.push(VarDeclarator { span: DUMMY_SP, name: Pat::Ident(BindingIdent { id, type_ann: None }), init: Some(Box::new(await_expr)), definite: false });
+                                            let call = require_call(specifier.clone(), src_span);
+                                            let mut await_expr = Expr::Await(AwaitExpr { span: src_span, arg: Box::new(call) });
+                                            if !self.config.no_interop {
+                                                await_expr = interop_default_call(await_expr);
+                                            }
+                                            decls.push(VarDeclarator { span: src_span, name: Pat::Ident(BindingIdent { id, type_ann: None }), init: Some(Box::new(await_expr)), definite: false });
                                         }
                                         ImportSpecifier::Named(named_spec) => {
                                             // const local = (await __require__('src')).imported;
@@ -134,37 +738,31 @@ impl VisitMut for AsyncRequireTransform {
                                                 Some(ModuleExportName::Str(s)) => s.value.clone(),
                                                 None => local.sym.clone(),
                                             };
-                                            let call = Expr::Call(CallExpr {
-                                                span: DUMMY_SP,
-                                                callee: Callee::Expr(Box::new(Expr::Ident(Ident::new("__require__".into(), DUMMY_SP)))),
-                                                args: vec![ExprOrSpread { spread: None, expr: Box::new(Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: import_decl.src.value.clone(), raw: None }))) }],
-                                                type_args: None,
-                                            });
-                                            let await_call = Expr::Await(AwaitExpr { span: DUMMY_SP, arg: Box::new(call) });
-                                            let member = Expr::Member(MemberExpr { span: DUMMY_SP, obj: Box::new(await_call), prop: MemberProp::Ident(Ident::new(imported, DUMMY_SP)), computed: false });
-                                            decls.push(VarDeclarator { span: DUMMY_SP, name: Pat::Ident(BindingIdent { id: local, type_ann: None }), init: Some(Box::new(member)), definite: false });
+                                            let call = require_call(specifier.clone(), src_span);
+                                            let await_call = Expr::Await(AwaitExpr { span: src_span, arg: Box::new(call) });
+                                            let member = Expr::Member(MemberExpr { span: named_spec.span, obj: Box::new(await_call), prop: MemberProp::Ident(Ident::new(imported, DUMMY_SP)), computed: false });
+                                            decls.push(VarDeclarator { span: named_spec.span, name: Pat::Ident(BindingIdent { id: local, type_ann: None }), init: Some(Box::new(member)), definite: false });
                                         }
                                         ImportSpecifier::Namespace(ns_spec) => {
                                             let id = ns_spec.local;
-                                            let call = Expr::Call(CallExpr {
-                                                span: DUMMY_SP,
-                                                callee: Callee::Expr(Box::new(Expr::Ident(Ident::new("__require__".into(), DUMMY_SP)))),
-                                                args: vec![ExprOrSpread { spread: None, expr: Box::new(Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: import_decl.src.value.clone(), raw: None }))) }],
-                                                type_args: None,
-                                            });
-                                            let await_expr = Expr::Await(AwaitExpr { span: DUMMY_SP, arg: Box::new(call) });
-                                            decls.push(VarDeclarator { span: DUMMY_SP, name: Pat::Ident(BindingIdent { id, type_ann: None }), init: Some(Box::new(await_expr)), definite: false });
+                                            let call = require_call(specifier.clone(), src_span);
+                                            let mut await_expr = Expr::Await(AwaitExpr { span: src_span, arg: Box::new(call) });
+                                            if !self.config.no_interop {
+                                                await_expr = interop_namespace_call(await_expr);
+                                            }
+                                            decls.push(VarDeclarator { span: ns_spec.span, name: Pat::Ident(BindingIdent { id, type_ann: None }), init: Some(Box::new(await_expr)), definite: false });
                                         }
                                     }
                                 }
-                                let var_decl = VarDecl { span: DUMMY_SP, kind: VarDeclKind::Const, declare: false, decls };
+                                let var_decl = VarDecl { span: decl_span, kind: VarDeclKind::Const, declare: false, decls };
                                 new_body.push(ModuleItem::Stmt(Stmt::Decl(Decl::Var(var_decl))));
-                            } else {
+                            } else if !had_specifiers {
                                 // import 'mod'; -> await __require__('mod');
-                                let call = Expr::Call(CallExpr { span: DUMMY_SP, callee: Callee::Expr(Box::new(Expr::Ident(Ident::new("__require__".into(), DUMMY_SP)))), args: vec![ExprOrSpread { spread: None, expr: Box::new(Expr::Lit(Lit::Str(Str { span: DUMMY_SP, value: import_decl.src.value.clone(), raw: None }))) }], type_args: None });
-                                let await_expr = Expr::Await(AwaitExpr { span: DUMMY_SP, arg: Box::new(call) });
-                                new_body.push(ModuleItem::Stmt(Stmt::Expr(ExprStmt { span: DUMMY_SP, expr: Box::new(await_expr) })));
+                                let call = require_call(specifier, src_span);
+                                let await_expr = Expr::Await(AwaitExpr { span: src_span, arg: Box::new(call) });
+                                new_body.push(ModuleItem::Stmt(Stmt::Expr(ExprStmt { span: decl_span, expr: Box::new(await_expr) })));
                             }
+                            // else: every specifier was type-only, nothing left to emit
                         }
                         ModuleItem::Stmt(mut stmt) => {
                             // visit inside statement to transform calls
@@ -175,59 +773,191 @@ impl VisitMut for AsyncRequireTransform {
                             // For other module decls (exports), handle some conversions
                             match other {
                                 ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(ed)) => {
-                                    // module.exports.default = <decl or expr>
-                                    let assign = Expr::Assign(AssignExpr { span: DUMMY_SP, op: AssignOp::Assign, left: PatOrExpr::Expr(Box::new(Expr::Member(MemberExpr { span: DUMMY_SP, obj: Box::new(Expr::Member(MemberExpr { span: DUMMY_SP, obj: Box::new(Expr::Ident(Ident::new("module".into(), DUMMY_SP))), prop: MemberProp::Ident(Ident::new("exports".into(), DUMMY_SP)), computed: false })), prop: MemberProp::Ident(Ident::new("default".into(), DUMMY_SP)), computed: false }))), right: Box::new(match ed.decl {
-                                            DefaultDecl::Expr(boxed_expr) => *boxed_expr,
-                                            DefaultDecl::Fn(f) => Expr::Fn(FnExpr { ident: f.ident.clone().map(|i| i.id), function: f.function }),
-                                            DefaultDecl::Class(c) => Expr::Class(ClassExpr { ident: c.ident.clone().map(|i| i.id), class: c.class }),
-                                        }));
-                                    new_body.push(ModuleItem::Stmt(Stmt::Expr(ExprStmt { span: DUMMY_SP, expr: Box::new(assign) })));
+                                    match ed.decl {
+                                        DefaultDecl::Expr(boxed_expr) => {
+                                            // no local binding to re-read later, so a plain
+                                            // assignment already reflects the final value
+                                            let mut expr = *boxed_expr;
+                                            // rewrite any require()/import() the expression
+                                            // closes over before it lands inside the factory
+                                            expr.visit_mut_with(self);
+                                            new_body.push(ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+                                                span: DUMMY_SP,
+                                                expr: Box::new(export_assign_expr("default".into(), expr)),
+                                            })));
+                                        }
+                                        DefaultDecl::Fn(f) => {
+                                            let mut function = f.function;
+                                            function.visit_mut_with(self);
+                                            if let Some(ident) = f.ident.clone() {
+                                                let name = ident.id;
+                                                new_body.push(ModuleItem::Stmt(Stmt::Decl(Decl::Fn(FnDecl { ident: name.clone(), declare: false, function }))));
+                                                new_body.push(ModuleItem::Stmt(export_getter_stmt("default".into(), name)));
+                                            } else {
+                                                let fn_expr = Expr::Fn(FnExpr { ident: None, function });
+                                                new_body.push(ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+                                                    span: DUMMY_SP,
+                                                    expr: Box::new(export_assign_expr("default".into(), fn_expr)),
+                                                })));
+                                            }
+                                        }
+                                        DefaultDecl::Class(c) => {
+                                            let mut class = c.class;
+                                            class.visit_mut_with(self);
+                                            if let Some(ident) = c.ident.clone() {
+                                                let name = ident.id;
+                                                new_body.push(ModuleItem::Stmt(Stmt::Decl(Decl::Class(ClassDecl { ident: name.clone(), declare: false, class }))));
+                                                new_body.push(ModuleItem::Stmt(export_getter_stmt("default".into(), name)));
+                                            } else {
+                                                let class_expr = Expr::Class(ClassExpr { ident: None, class });
+                                                new_body.push(ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+                                                    span: DUMMY_SP,
+                                                    expr: Box::new(export_assign_expr("default".into(), class_expr)),
+                                                })));
+                                            }
+                                        }
+                                    }
+                                }
+                                ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(named)) if named.type_only => {
+                                    // `export type { ... } from 'mod'` has no runtime
+                                    // representation; drop it entirely.
+                                }
+                                ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(named)) if named.src.is_some() => {
+                                    // re-export: `export { a, b as c } from 'src';`,
+                                    // `export { default as X } from 'src';`,
+                                    // `export * as ns from 'src';`
+                                    let src = named.src.clone().unwrap();
+                                    let specifier = self.resolve_specifier(&src.value);
+                                    let tmp_ident = Ident::new(self.next_tmp().into(), DUMMY_SP);
+                                    let await_expr = Expr::Await(AwaitExpr { span: src.span, arg: Box::new(require_call(specifier, src.span)) });
+                                    let var_decl = VarDecl {
+                                        span: named.span,
+                                        kind: VarDeclKind::Const,
+                                        declare: false,
+                                        decls: vec![VarDeclarator { span: named.span, name: Pat::Ident(BindingIdent { id: tmp_ident.clone(), type_ann: None }), init: Some(Box::new(await_expr)), definite: false }],
+                                    };
+                                    new_body.push(ModuleItem::Stmt(Stmt::Decl(Decl::Var(var_decl))));
+
+                                    for spec in named.specifiers {
+                                        match spec {
+                                            ExportSpecifier::Named(named_spec) => {
+                                                let orig_name = match &named_spec.orig {
+                                                    ModuleExportName::Ident(i) => i.sym.clone(),
+                                                    ModuleExportName::Str(s) => s.value.clone(),
+                                                };
+                                                let exported_name = match &named_spec.exported {
+                                                    Some(ModuleExportName::Ident(i)) => i.sym.clone(),
+                                                    Some(ModuleExportName::Str(s)) => s.value.clone(),
+                                                    None => orig_name.clone(),
+                                                };
+                                                let member = Expr::Member(MemberExpr { span: DUMMY_SP, obj: Box::new(Expr::Ident(tmp_ident.clone())), prop: MemberProp::Ident(Ident::new(orig_name, DUMMY_SP)), computed: false });
+                                                new_body.push(ModuleItem::Stmt(Stmt::Expr(ExprStmt { span: DUMMY_SP, expr: Box::new(export_assign_expr(exported_name, member)) })));
+                                            }
+                                            ExportSpecifier::Namespace(ns) => {
+                                                let exported_name = match &ns.name {
+                                                    ModuleExportName::Ident(i) => i.sym.clone(),
+                                                    ModuleExportName::Str(s) => s.value.clone(),
+                                                };
+                                                new_body.push(ModuleItem::Stmt(Stmt::Expr(ExprStmt { span: DUMMY_SP, expr: Box::new(export_assign_expr(exported_name, Expr::Ident(tmp_ident.clone()))) })));
+                                            }
+                                            ExportSpecifier::Default(_) => {
+                                                // `export v from 'mod'` is a stage-1 proposal swc
+                                                // doesn't parse by default; nothing to lower here
+                                            }
+                                        }
+                                    }
                                 }
                                 ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(named)) => {
                                     if let Some(decl) = named.decl {
                                         match decl {
-                                            Decl::Var(var_decl) => {
-                                                // keep the original var decl and add assignments
+                                            Decl::Var(mut var_decl) => {
+                                                // visit initializers first so any require()/import()
+                                                // they close over gets rewritten before landing inside
+                                                // the factory, then keep the original var decl so
+                                                // reassignments of exported `let`/`var` bindings stay
+                                                // live, and register a getter for every bound name
+                                                // (including destructured ones) instead of a value copy
+                                                var_decl.visit_mut_with(self);
                                                 new_body.push(ModuleItem::Stmt(Stmt::Decl(Decl::Var(var_decl.clone()))));
-                                                for vd in var_decl.decls {
-                                                    if let Pat::Ident(BindingIdent { id, .. }) = vd.name {
-                                                        let assign = Expr::Assign(AssignExpr { span: DUMMY_SP, op: AssignOp::Assign, left: PatOrExpr::Expr(Box::new(Expr::Member(MemberExpr { span: DUMMY_SP, obj: Box::new(Expr::Member(MemberExpr { span: DUMMY_SP, obj: Box::new(Expr::Ident(Ident::new("module".into(), DUMMY_SP))), prop: MemberProp::Ident(Ident::new("exports".into(), DUMMY_SP)), computed: false })), prop: MemberProp::Ident(Ident::new(id.sym.clone(), DUMMY_SP)), computed: false }))), right: Box::new(Expr::Ident(Ident::new(id.sym.clone(), DUMMY_SP)))});
-                                                        new_body.push(ModuleItem::Stmt(Stmt::Expr(ExprStmt { span: DUMMY_SP, expr: Box::new(assign) })));
+                                                for vd in &var_decl.decls {
+                                                    let mut idents = Vec::new();
+                                                    collect_bound_idents(&vd.name, &mut idents);
+                                                    for id in idents {
+                                                        new_body.push(ModuleItem::Stmt(export_getter_stmt(id.sym.clone(), id)));
                                                     }
                                                 }
                                             }
-                                            Decl::Fn(fn_decl) => {
-                                                let name = fn_decl.ident.clone().unwrap().id.sym.clone();
+                                            Decl::Fn(mut fn_decl) => {
+                                                fn_decl.visit_mut_with(self);
+                                                let name = fn_decl.ident.clone().unwrap();
                                                 new_body.push(ModuleItem::Stmt(Stmt::Decl(Decl::Fn(fn_decl.clone()))));
-                                                let assign = Expr::Assign(AssignExpr { span: DUMMY_SP, op: AssignOp::Assign, left: PatOrExpr::Expr(Box::new(Expr::Member(MemberExpr { span: DUMMY_SP, obj: Box::new(Expr::Member(MemberExpr { span: DUMMY_SP, obj: Box::new(Expr::Ident(Ident::new("module".into(), DUMMY_SP))), prop: MemberProp::Ident(Ident::new("exports".into(), DUMMY_SP)), computed: false })), prop: MemberProp::Ident(Ident::new(name.clone(), DUMMY_SP)), computed: false }))), right: Box::new(Expr::Ident(Ident::new(name.clone(), DUMMY_SP)))});
-                                                new_body.push(ModuleItem::Stmt(Stmt::Expr(ExprStmt { span: DUMMY_SP, expr: Box::new(assign) })));
+                                                new_body.push(ModuleItem::Stmt(export_getter_stmt(name.sym.clone(), name)));
+                                            }
+                                            Decl::Class(mut class_decl) => {
+                                                class_decl.visit_mut_with(self);
+                                                let name = class_decl.ident.clone();
+                                                new_body.push(ModuleItem::Stmt(Stmt::Decl(Decl::Class(class_decl.clone()))));
+                                                new_body.push(ModuleItem::Stmt(export_getter_stmt(name.sym.clone(), name)));
                                             }
                                             _ => {
                                                 new_body.push(ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(named)));
                                             }
                                         }
                                     } else {
-                                        // export { a, b }
-                                        let mut assigns: Vec<ModuleItem> = vec![];
+                                        // export { a, b as c, d as "string-name" }
                                         for spec in named.specifiers {
                                             if let ExportSpecifier::Namespace(ns) = spec {
-                                                assigns.push(ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(ExportNamedDecl { span: DUMMY_SP, decl: None, specifiers: vec![], src: None })));
+                                                new_body.push(ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(ExportNamedDecl { span: DUMMY_SP, decl: None, specifiers: vec![ExportSpecifier::Namespace(ns)], src: None })));
                                             } else if let ExportSpecifier::Named(named_spec) = spec {
-                                                let exported = named_spec.exported.sym.clone();
-                                                let local = named_spec.orig.sym.clone();
-                                                let assign = Expr::Assign(AssignExpr { span: DUMMY_SP, op: AssignOp::Assign, left: PatOrExpr::Expr(Box::new(Expr::Member(MemberExpr { span: DUMMY_SP, obj: Box::new(Expr::Member(MemberExpr { span: DUMMY_SP, obj: Box::new(Expr::Ident(Ident::new("module".into(), DUMMY_SP))), prop: MemberProp::Ident(Ident::new("exports".into(), DUMMY_SP)), computed: false })), prop: MemberProp::Ident(Ident::new(exported.clone(), DUMMY_SP)), computed: false }))), right: Box::new(Expr::Ident(Ident::new(local.clone(), DUMMY_SP)))});
-                                                new_body.push(ModuleItem::Stmt(Stmt::Expr(ExprStmt { span: DUMMY_SP, expr: Box::new(assign) })));
+                                                let local = match &named_spec.orig {
+                                                    ModuleExportName::Ident(i) => i.clone(),
+                                                    ModuleExportName::Str(s) => Ident::new(s.value.clone(), s.span),
+                                                };
+                                                let exported_name = match &named_spec.exported {
+                                                    Some(ModuleExportName::Ident(i)) => i.sym.clone(),
+                                                    Some(ModuleExportName::Str(s)) => s.value.clone(),
+                                                    None => local.sym.clone(),
+                                                };
+                                                new_body.push(ModuleItem::Stmt(export_getter_stmt(exported_name, local)));
                                             }
                                         }
-                                        // append assigns
                                     }
                                 }
+                                ModuleItem::ModuleDecl(ModuleDecl::ExportAll(export_all)) if export_all.type_only => {
+                                    // `export type * from 'mod'` has no runtime representation
+                                }
+                                ModuleItem::ModuleDecl(ModuleDecl::ExportAll(export_all)) => {
+                                    // export * from 'src';
+                                    let specifier = self.resolve_specifier(&export_all.src.value);
+                                    let tmp_ident = Ident::new(self.next_tmp().into(), DUMMY_SP);
+                                    let await_expr = Expr::Await(AwaitExpr { span: export_all.src.span, arg: Box::new(require_call(specifier, export_all.src.span)) });
+                                    let var_decl = VarDecl {
+                                        span: export_all.span,
+                                        kind: VarDeclKind::Const,
+                                        declare: false,
+                                        decls: vec![VarDeclarator { span: export_all.span, name: Pat::Ident(BindingIdent { id: tmp_ident.clone(), type_ann: None }), init: Some(Box::new(await_expr)), definite: false }],
+                                    };
+                                    new_body.push(ModuleItem::Stmt(Stmt::Decl(Decl::Var(var_decl))));
+                                    new_body.push(ModuleItem::Stmt(export_star_loop_stmt(tmp_ident)));
+                                }
                                 _ => new_body.push(other),
                             }
                         }
                     }
                 }
-                m.body = new_body;
+                // Everything left should be a plain Stmt; the rare leftover
+                // ModuleDecl (e.g. a re-export shape we don't lower, like a
+                // bare `export {}` namespace placeholder) has no runtime
+                // representation inside the factory body and is dropped.
+                let stmts: Vec<Stmt> = new_body
+                    .into_iter()
+                    .filter_map(|item| match item {
+                        ModuleItem::Stmt(s) => Some(s),
+                        ModuleItem::ModuleDecl(_) => None,
+                    })
+                    .collect();
+                let factory_stmt = wrap_in_define_factory(self.config.module_id.clone().into(), stmts);
+                m.body = vec![ModuleItem::Stmt(factory_stmt)];
             }
             Program::Script(_) => {}
         }
@@ -235,23 +965,108 @@ impl VisitMut for AsyncRequireTransform {
 
     fn visit_mut_call_expr(&mut self, n: &mut CallExpr) {
         // transform require('x') to __require__('x') and import() to __import__
+        let mut is_require_or_import = false;
         if let Callee::Expr(expr) = &mut n.callee {
             if let Expr::Ident(ident) = &mut **expr {
                 if ident.sym == *"require" {
                     ident.sym = "__require__".into();
+                    is_require_or_import = true;
                 }
             }
         }
-        if let Callee::Import(_) = &n.callee {
-            n.callee = Callee::Expr(Box::new(Expr::Ident(Ident::new("__import__".into(), DUMMY_SP))));
+        if let Callee::Import(import) = &n.callee {
+            // reuse `import`'s own span so the rewritten callee still points
+            // at the `import` keyword the author wrote
+            n.callee = Callee::Expr(Box::new(Expr::Ident(Ident::new("__import__".into(), import.span))));
+            is_require_or_import = true;
+        }
+        if is_require_or_import {
+            if let Some(ExprOrSpread { expr, spread: None }) = n.args.first_mut() {
+                if let Expr::Lit(Lit::Str(s)) = &mut **expr {
+                    s.value = self.resolve_specifier(&s.value);
+                    s.raw = None;
+                }
+            }
         }
+        // rewrite require()/import() calls nested in the callee or arguments
+        n.visit_mut_children_with(self);
     }
 }
 
+/// Options accepted alongside the AST JSON at the `process_plugin` boundary.
+///
+/// `source_map` is intentionally not honoured here — see [`process_plugin`].
+/// It stays in the struct (instead of being removed outright) so hosts that
+/// already send `{ sourceMap: true }` get a clear panic/error rather than a
+/// silently-ignored field, and so the in-process [`transform_with_source_map`]
+/// path documents the option it replaces.
+#[derive(serde::Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ProcessOptions {
+    #[serde(default)]
+    source_map: bool,
+}
+
+/// Emits `program` through the codegen `Emitter`/`JsWriter` pipeline with a
+/// `SourceMappingConfig`, returning the raw source-map JSON. `cm` must be the
+/// same `SourceMap` the program's spans were allocated against — the spans
+/// threaded through `AsyncRequireTransform` are only meaningful relative to
+/// it.
+fn build_source_map(cm: &Lrc<SourceMap>, program: &Program) -> String {
+    let mut buf = vec![];
+    let mut raw_mappings = vec![];
+    {
+        let writer = JsWriter::new(cm.clone(), "\n", &mut buf, Some(&mut raw_mappings));
+        let mut emitter = Emitter {
+            cfg: CodegenConfig::default(),
+            comments: None,
+            cm: cm.clone(),
+            wr: writer,
+        };
+        emitter.emit_program(program).expect("codegen should not fail on a transformed AST");
+    }
+    let source_map = cm.build_source_map(&raw_mappings);
+    let mut map_buf = vec![];
+    source_map.to_writer(&mut map_buf).expect("source map serialization should not fail");
+    String::from_utf8(map_buf).expect("source map JSON is always valid UTF-8")
+}
+
+/// In-process entry point for Rust embedders that hold the real `Lrc<SourceMap>`
+/// the program's spans were allocated against (e.g. a host driving this crate
+/// directly as a library, or a swc plugin runtime that has its own `cm`).
+/// Unlike [`process_plugin`], `cm` here is the actual source map the spans
+/// resolve against, so the emitted mappings are meaningful.
+pub fn transform_with_source_map(
+    cm: &Lrc<SourceMap>,
+    mut program: Program,
+    config: AsyncRequireConfig,
+) -> (Program, String) {
+    let mut transformer = AsyncRequireTransform::new(config);
+    program.visit_mut_with(&mut transformer);
+    let map = build_source_map(cm, &program);
+    (program, map)
+}
+
+/// Wasm boundary for hosts that hand over the AST (and get it back) as JSON.
+///
+/// `options.sourceMap` is not supported here: the spans inside `program` are
+/// only meaningful relative to the host's own `Lrc<SourceMap>`, and a bare
+/// `JsValue` can't carry that handle across the Wasm boundary. Building a
+/// source map against a freshly-created, empty `SourceMap` would produce
+/// mappings that don't correspond to the host's actual source — worse than
+/// returning none at all. A host that needs a real source map should call
+/// [`transform_with_source_map`] in-process, where `cm` is available.
 #[wasm_bindgen]
-pub fn process_plugin(program: JsValue) -> JsValue {
+pub fn process_plugin(program: JsValue, options: JsValue) -> JsValue {
+    let opts: ProcessOptions = options.into_serde().unwrap_or_default();
+    if opts.source_map {
+        wasm_bindgen::throw_str(
+            "process_plugin: sourceMap is not supported across the Wasm boundary; \
+             use transform_with_source_map from an in-process host that owns the SourceMap",
+        );
+    }
     let mut program: Program = program.into_serde().unwrap();
-    let mut transformer = AsyncRequireTransform::new();
+    let mut transformer = AsyncRequireTransform::new(AsyncRequireConfig::default());
     program.visit_mut_with(&mut transformer);
     JsValue::from_serde(&program).unwrap()
 }